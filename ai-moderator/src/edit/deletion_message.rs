@@ -61,6 +61,8 @@ pub async fn handle_input(
         Attachment::AudioFileId(audio.file.id.clone())
     } else if let Some(document) = message.document() {
         Attachment::DocumentFileId(document.file.id.clone(), "file".to_string())
+    } else if let Some(sticker) = message.sticker() {
+        Attachment::StickerFileId(sticker.file.id.clone())
     } else {
         Attachment::None
     };
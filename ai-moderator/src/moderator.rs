@@ -126,6 +126,7 @@ pub async fn open_main(
             Attachment::DocumentUrl(_, _)
             | Attachment::DocumentText(_, _)
             | Attachment::DocumentFileId(_, _) => "\n\\+ file",
+            Attachment::StickerFileId(_) => "\n\\+ sticker",
         };
     let deletion_message = expandable_blockquote(&deletion_message);
     let message =
@@ -108,7 +108,7 @@ impl BurrowLiquidationsModule {
                         let collateral_sum: f64 =
                             event_data.collateral_sum.to_string().parse().unwrap();
                         let repaid_sum = event_data.repaid_sum.to_string().parse().unwrap();
-                        tokio::spawn(async move {
+                        self.xeon.spawn_notification_task(async move {
                             let Some(bot) = xeon.bot(&bot_id) else {
                                 return;
                             };
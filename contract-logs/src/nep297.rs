@@ -88,7 +88,7 @@ impl ContractLogsNep297Module {
                         let version = event.event_version.clone();
                         let event = event.event_event.clone();
                         let bot_id = *bot_id;
-                        tokio::spawn(async move {
+                        self.xeon.spawn_notification_task(async move {
                             let Some(bot) = xeon.bot(&bot_id) else {
                                 return;
                             };
@@ -78,7 +78,7 @@ impl ContractLogsTextModule {
                         let log_text = event.log_text.clone();
                         let transaction_id = event.transaction_id;
                         let bot_id = *bot_id;
-                        tokio::spawn(async move {
+                        self.xeon.spawn_notification_task(async move {
                             let Some(bot) = xeon.bot(&bot_id) else {
                                 return;
                             };
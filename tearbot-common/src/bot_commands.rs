@@ -159,6 +159,8 @@ pub enum TgCommand {
     #[cfg(feature = "ft-buybot-module")]
     FtNotificationsSubscriptionAttachmentAnimation(NotificationDestination, Token, usize),
     #[cfg(feature = "ft-buybot-module")]
+    FtNotificationsSubscriptionAttachmentSticker(NotificationDestination, Token, usize),
+    #[cfg(feature = "ft-buybot-module")]
     FtNotificationsPreview(NotificationDestination, Token),
     #[cfg(feature = "ft-buybot-module")]
     FtNotificationsEditButtons(NotificationDestination, Token),
@@ -555,6 +557,7 @@ pub enum TgCommand {
     OpenAccountConnectionMenu,
     DisconnectAccount,
     SetReferralNotifications(bool),
+    SetNotificationLimitNotice(NotificationDestination, bool),
     #[cfg(feature = "ft-buybot-module")]
     FtNotificationsEnableSubscriptionLpAdd(NotificationDestination, Token),
     #[cfg(feature = "ft-buybot-module")]
@@ -1248,6 +1251,8 @@ pub enum MessageCommand {
     #[cfg(feature = "ft-buybot-module")]
     FtNotificationsSubscriptionAttachmentAnimation(NotificationDestination, Token, usize),
     #[cfg(feature = "ft-buybot-module")]
+    FtNotificationsSubscriptionAttachmentSticker(NotificationDestination, Token, usize),
+    #[cfg(feature = "ft-buybot-module")]
     FtNotificationsEditButtons(NotificationDestination, Token),
     #[cfg(feature = "ft-buybot-module")]
     FtNotificationsEditLinks(NotificationDestination, Token),
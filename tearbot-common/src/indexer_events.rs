@@ -17,6 +17,115 @@ use intear_events::events::{
     trade::{trade_pool_change::TradePoolChangeEvent, trade_swap::TradeSwapEvent},
 };
 
+/// How long to wait before reconnecting to an event stream after a failure
+#[cfg(any(feature = "redis-events", feature = "websocket-events"))]
+const RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Minimum interval between two "event queue is full" warnings
+#[cfg(any(feature = "redis-events", feature = "websocket-events"))]
+const QUEUE_FULL_WARNING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct IndexerStreamHealth {
+    /// For redis streams, this is only set once the first event arrives, since
+    /// the consumer doesn't report when it connects
+    pub connected: bool,
+    pub last_connected: Option<std::time::Instant>,
+    pub last_event: Option<std::time::Instant>,
+    pub reconnects: usize,
+}
+
+lazy_static::lazy_static! {
+    static ref STREAM_HEALTH: dashmap::DashMap<String, IndexerStreamHealth> =
+        dashmap::DashMap::new();
+}
+
+#[cfg(any(feature = "redis-events", feature = "websocket-events"))]
+static LAST_QUEUE_FULL_WARNING: std::sync::Mutex<Option<std::time::Instant>> =
+    std::sync::Mutex::new(None);
+#[cfg(any(feature = "redis-events", feature = "websocket-events"))]
+static QUEUE_FULL_SINCE_LAST_WARNING: std::sync::atomic::AtomicUsize =
+    std::sync::atomic::AtomicUsize::new(0);
+
+/// Connection state of every event stream, sorted by stream name
+pub fn indexer_stream_health() -> Vec<(String, IndexerStreamHealth)> {
+    let mut health = STREAM_HEALTH
+        .iter()
+        .map(|entry| (entry.key().clone(), entry.value().clone()))
+        .collect::<Vec<_>>();
+    health.sort_by(|(a, _), (b, _)| a.cmp(b));
+    health
+}
+
+#[cfg(any(feature = "redis-events", feature = "websocket-events"))]
+fn set_stream_connected(stream: &str, connected: bool) {
+    let mut health = STREAM_HEALTH
+        .entry(stream.to_string())
+        .or_insert(IndexerStreamHealth {
+            connected: false,
+            last_connected: None,
+            last_event: None,
+            reconnects: 0,
+        });
+    if connected && !health.connected {
+        if health.last_connected.is_some() {
+            health.reconnects += 1;
+            match health.last_event {
+                Some(last_event) => log::warn!(
+                    "Reconnected to event stream {stream}, last event was {:?} ago, events in between are lost",
+                    last_event.elapsed()
+                ),
+                None => log::warn!("Reconnected to event stream {stream}"),
+            }
+        }
+        health.last_connected = Some(std::time::Instant::now());
+    }
+    health.connected = connected;
+}
+
+#[cfg(any(feature = "redis-events", feature = "websocket-events"))]
+fn warn_queue_full(stream: &str) {
+    use std::sync::atomic::Ordering;
+
+    let times = QUEUE_FULL_SINCE_LAST_WARNING.fetch_add(1, Ordering::Relaxed) + 1;
+    let mut last_warning = LAST_QUEUE_FULL_WARNING.lock().unwrap();
+    if last_warning.is_some_and(|last| last.elapsed() < QUEUE_FULL_WARNING_INTERVAL) {
+        return;
+    }
+    *last_warning = Some(std::time::Instant::now());
+    QUEUE_FULL_SINCE_LAST_WARNING.store(0, Ordering::Relaxed);
+    log::warn!(
+        "Event queue is full, {stream} is waiting for handlers to catch up ({times} times since the last warning)"
+    );
+}
+
+/// Records the event and sends it to handlers. If handlers can't keep up, waits for them
+/// instead of buffering more events. Returns `false` if the handler loop is gone.
+#[cfg(any(feature = "redis-events", feature = "websocket-events"))]
+async fn forward_event(
+    tx: &tokio::sync::mpsc::Sender<IndexerEvent>,
+    stream: &str,
+    event: IndexerEvent,
+) -> bool {
+    if !STREAM_HEALTH
+        .get(stream)
+        .is_some_and(|health| health.connected)
+    {
+        set_stream_connected(stream, true);
+    }
+    if let Some(mut health) = STREAM_HEALTH.get_mut(stream) {
+        health.last_event = Some(std::time::Instant::now());
+    }
+    match tx.try_send(event) {
+        Ok(()) => true,
+        Err(tokio::sync::mpsc::error::TrySendError::Full(event)) => {
+            warn_queue_full(stream);
+            tx.send(event).await.is_ok()
+        }
+        Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => false,
+    }
+}
+
 #[cfg(any(feature = "redis-events", feature = "websocket-events"))]
 pub async fn start_stream(state: std::sync::Arc<crate::xeon::XeonState>) {
     use intear_events::events::ft::ft_transfer::FtTransferEvent;
@@ -215,22 +324,7 @@ pub async fn start_stream(state: std::sync::Arc<crate::xeon::XeonState>) {
                 last_ping = std::time::Instant::now();
             }
 
-            for handler in state.indexer_event_handlers().await.iter() {
-                let now = std::time::Instant::now();
-                log::debug!(target: "indexer_events", "Handling event {event:?}");
-                if let Err(err) = handler.handle_event(&event).await {
-                    log::error!("Failed to handle event {event:?}: {err:?}");
-                }
-                log::debug!(target: "indexer_events", "Event Handled");
-                let elapsed = now.elapsed();
-                const HANDLER_WARNING_THRESHOLD: std::time::Duration =
-                    std::time::Duration::from_millis(10);
-                if elapsed > HANDLER_WARNING_THRESHOLD {
-                    log::warn!(
-                        "Event handler took more than {HANDLER_WARNING_THRESHOLD:?} to process event {event:?}: {elapsed:?}"
-                    );
-                }
-            }
+            state.dispatch_indexer_event(&event).await;
         }
     });
 }
@@ -249,26 +343,51 @@ async fn stream_events<
     connection: redis::aio::ConnectionManager,
 ) {
     use std::convert::Infallible;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
 
     let id = if testnet {
         format!("{event_id}_testnet")
     } else {
         event_id.to_string()
     };
-    inevents_redis_old::RedisEventStream::new(connection, id)
-        .start_reading_events(
-            "xeon",
-            move |event: E| {
-                let tx = tx.clone();
-                async move {
-                    tx.send(convert(event)).await.unwrap();
-                    Ok::<(), Infallible>(())
-                }
-            },
-            || false,
-        )
-        .await
-        .unwrap();
+    // Set when the handler loop is gone, there's no point in reading more events then
+    let stopped = Arc::new(AtomicBool::new(false));
+    loop {
+        // Marked as connected by `forward_event` once the first event arrives
+        let tx = tx.clone();
+        let stream = id.clone();
+        let stopped_in_handler = Arc::clone(&stopped);
+        let stopped_in_check = Arc::clone(&stopped);
+        if let Err(err) = inevents_redis_old::RedisEventStream::new(connection.clone(), id.clone())
+            .start_reading_events(
+                "xeon",
+                move |event: E| {
+                    let tx = tx.clone();
+                    let stream = stream.clone();
+                    let stopped = Arc::clone(&stopped_in_handler);
+                    async move {
+                        if !forward_event(&tx, &stream, convert(event)).await
+                            && !stopped.swap(true, Ordering::Relaxed)
+                        {
+                            log::error!("Event handler loop is gone, stopping {stream}");
+                        }
+                        Ok::<(), Infallible>(())
+                    }
+                },
+                move || stopped_in_check.load(Ordering::Relaxed),
+            )
+            .await
+        {
+            log::error!("Event stream {id} stopped: {err:?}");
+        }
+        set_stream_connected(&id, false);
+        if stopped.load(Ordering::Relaxed) {
+            return;
+        }
+        log::warn!("Reconnecting to event stream {id}");
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
 }
 
 #[cfg(feature = "redis-events")]
@@ -282,26 +401,51 @@ async fn stream_v3_events<
     connection: redis::aio::ConnectionManager,
 ) {
     use std::convert::Infallible;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
 
     let id = if testnet {
         format!("{event_id}_testnet")
     } else {
         event_id.to_string()
     };
-    inevents_redis::RedisEventStream::new(connection, id)
-        .start_reading_events(
-            "xeon",
-            move |event: E| {
-                let tx = tx.clone();
-                async move {
-                    tx.send(convert(event)).await.unwrap();
-                    Ok::<(), Infallible>(())
-                }
-            },
-            || false,
-        )
-        .await
-        .unwrap();
+    // Set when the handler loop is gone, there's no point in reading more events then
+    let stopped = Arc::new(AtomicBool::new(false));
+    loop {
+        // Marked as connected by `forward_event` once the first event arrives
+        let tx = tx.clone();
+        let stream = id.clone();
+        let stopped_in_handler = Arc::clone(&stopped);
+        let stopped_in_check = Arc::clone(&stopped);
+        if let Err(err) = inevents_redis::RedisEventStream::new(connection.clone(), id.clone())
+            .start_reading_events(
+                "xeon",
+                move |event: E| {
+                    let tx = tx.clone();
+                    let stream = stream.clone();
+                    let stopped = Arc::clone(&stopped_in_handler);
+                    async move {
+                        if !forward_event(&tx, &stream, convert(event)).await
+                            && !stopped.swap(true, Ordering::Relaxed)
+                        {
+                            log::error!("Event handler loop is gone, stopping {stream}");
+                        }
+                        Ok::<(), Infallible>(())
+                    }
+                },
+                move || stopped_in_check.load(Ordering::Relaxed),
+            )
+            .await
+        {
+            log::error!("Event stream {id} stopped: {err:?}");
+        }
+        set_stream_connected(&id, false);
+        if stopped.load(Ordering::Relaxed) {
+            return;
+        }
+        log::warn!("Reconnecting to event stream {id}");
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
 }
 
 #[cfg(feature = "websocket-events")]
@@ -317,43 +461,59 @@ async fn stream_events<
     use tokio_tungstenite::tungstenite::Message;
 
     let events = if testnet { "events-testnet" } else { "events" };
-    'outer: loop {
-        let (mut stream, _) = tokio_tungstenite::connect_async(format!(
+    let stream_name = format!("{events}/{event_id}");
+    loop {
+        let mut stream = match tokio_tungstenite::connect_async(format!(
             "wss://ws-events.intear.tech/{events}/{event_id}"
         ))
         .await
-        .expect("Failed to connect to event stream");
-        stream
-            .send(Message::Text("{}".to_string()))
-            .await
-            .expect("Failed to send empty filter");
+        {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                log::error!("Failed to connect to event stream {stream_name}: {err}");
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+        if let Err(err) = stream.send(Message::Text("{}".to_string())).await {
+            log::error!("Failed to send filter to event stream {stream_name}: {err}");
+            tokio::time::sleep(RECONNECT_DELAY).await;
+            continue;
+        }
+        set_stream_connected(&stream_name, true);
         while let Some(message) = stream.next().await {
             let Ok(msg) = message else {
-                continue 'outer;
+                break;
             };
             match msg {
                 Message::Close(_) => {
-                    log::warn!("Event stream {events}/{event_id} closed");
-                    break 'outer;
+                    log::warn!("Event stream {stream_name} closed");
+                    break;
                 }
                 tokio_tungstenite::tungstenite::Message::Ping(data) => {
-                    stream
-                        .send(Message::Pong(data))
-                        .await
-                        .expect("Failed to pong");
+                    if stream.send(Message::Pong(data)).await.is_err() {
+                        break;
+                    }
                 }
                 Message::Pong(_) => {}
-                Message::Text(text) => {
-                    let event: E =
-                        serde_json::from_str(&text).expect("Failed to parse message as json");
-                    let event = convert(event);
-                    tx.send(event).await.expect("Failed to send event");
-                }
+                Message::Text(text) => match serde_json::from_str::<E>(&text) {
+                    Ok(event) => {
+                        if !forward_event(&tx, &stream_name, convert(event)).await {
+                            log::error!("Event handler loop is gone, stopping {stream_name}");
+                            return;
+                        }
+                    }
+                    Err(err) => {
+                        log::error!("Failed to parse event from {stream_name}: {err}");
+                    }
+                },
                 Message::Binary(_) => {}
                 Message::Frame(_) => unreachable!(),
             }
         }
-        log::warn!("Reconnecting to event stream {events}/{event_id}");
+        set_stream_connected(&stream_name, false);
+        log::warn!("Reconnecting to event stream {stream_name}");
+        tokio::time::sleep(RECONNECT_DELAY).await;
     }
 }
 
@@ -370,45 +530,64 @@ async fn stream_v3_events<
     use tokio_tungstenite::tungstenite::Message;
 
     let events = if testnet { "events-testnet" } else { "events" };
-    'outer: loop {
-        let (mut stream, _) = tokio_tungstenite::connect_async(format!(
+    let stream_name = format!("{events}/{event_id}");
+    loop {
+        let mut stream = match tokio_tungstenite::connect_async(format!(
             "wss://ws-events-v3.intear.tech/{events}/{event_id}"
         ))
         .await
-        .expect("Failed to connect to event stream");
-        stream
+        {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                log::error!("Failed to connect to event stream {stream_name}: {err}");
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+        if let Err(err) = stream
             .send(Message::Text(r#"{"And":[]}"#.to_string()))
             .await
-            .expect("Failed to send empty filter");
+        {
+            log::error!("Failed to send filter to event stream {stream_name}: {err}");
+            tokio::time::sleep(RECONNECT_DELAY).await;
+            continue;
+        }
+        set_stream_connected(&stream_name, true);
         while let Some(message) = stream.next().await {
             let Ok(msg) = message else {
-                continue 'outer;
+                break;
             };
             match msg {
                 Message::Close(_) => {
-                    log::warn!("Event stream {events}/{event_id} closed");
-                    break 'outer;
+                    log::warn!("Event stream {stream_name} closed");
+                    break;
                 }
                 tokio_tungstenite::tungstenite::Message::Ping(data) => {
-                    stream
-                        .send(Message::Pong(data))
-                        .await
-                        .expect("Failed to pong");
+                    if stream.send(Message::Pong(data)).await.is_err() {
+                        break;
+                    }
                 }
                 Message::Pong(_) => {}
-                Message::Text(text) => {
-                    let events: Vec<E> =
-                        serde_json::from_str(&text).expect("Failed to parse message as json");
-                    for event in events {
-                        let event = convert(event);
-                        tx.send(event).await.expect("Failed to send event");
+                Message::Text(text) => match serde_json::from_str::<Vec<E>>(&text) {
+                    Ok(events) => {
+                        for event in events {
+                            if !forward_event(&tx, &stream_name, convert(event)).await {
+                                log::error!("Event handler loop is gone, stopping {stream_name}");
+                                return;
+                            }
+                        }
                     }
-                }
+                    Err(err) => {
+                        log::error!("Failed to parse events from {stream_name}: {err}");
+                    }
+                },
                 Message::Binary(_) => {}
                 Message::Frame(_) => unreachable!(),
             }
         }
-        log::warn!("Reconnecting to event stream {events}/{event_id}");
+        set_stream_connected(&stream_name, false);
+        log::warn!("Reconnecting to event stream {stream_name}");
+        tokio::time::sleep(RECONNECT_DELAY).await;
     }
 }
 
@@ -464,4 +643,12 @@ impl IndexerEvent {
 #[async_trait]
 pub trait IndexerEventHandler: Send + Sync + 'static {
     async fn handle_event(&self, event: &IndexerEvent) -> Result<(), anyhow::Error>;
+
+    /// Whether `XeonState::dispatch_simulated_indexer_event` may pass events to this
+    /// handler. Only return true if the handler keeps no state from events, and sends
+    /// notifications from the handling task or `spawn_notification_task` after checking
+    /// `reached_notification_limit`, so they stay limited to the simulation target.
+    fn supports_simulation(&self) -> bool {
+        false
+    }
 }
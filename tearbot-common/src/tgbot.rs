@@ -1,9 +1,10 @@
+use std::cell::Cell;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{
     collections::HashMap,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
 use chrono::DateTime;
@@ -21,7 +22,7 @@ use serde::{Deserialize, Serialize};
 use teloxide::payloads::SendMessageSetters;
 use teloxide::payloads::SendPhotoSetters;
 use teloxide::payloads::{AnswerInlineQuerySetters, SendAudioSetters};
-use teloxide::payloads::{EditMessageTextSetters, SendDocumentSetters};
+use teloxide::payloads::{EditMessageTextSetters, SendDocumentSetters, SendStickerSetters};
 use teloxide::prelude::dptree;
 use teloxide::prelude::CallbackQuery;
 use teloxide::prelude::Dispatcher;
@@ -45,7 +46,7 @@ use crate::utils::format_duration;
 use crate::utils::requests::fetch_file_cached_1d;
 use crate::utils::store::PersistentCachedStore;
 use crate::utils::tokens::{StringifiedBalance, WRAP_NEAR};
-use crate::xeon::XeonState;
+use crate::xeon::{simulation_target, XeonState};
 use crate::{
     bot_commands::{MessageCommand, PaymentReference, TgCommand},
     utils::store::PersistentUncachedStore,
@@ -59,10 +60,22 @@ macro_rules! attach_thread_id {
         }
         request
     }};
+    ($request: expr, $chat_id: expr, $disable_notification: expr) => {{
+        attach_thread_id!($request, $chat_id).disable_notification($disable_notification)
+    }};
 }
 
 pub type TgBot = CacheMe<Throttle<Bot>>;
 
+/// How many callbacks can be parsed while handling a single update, including callbacks
+/// that handlers call recursively to re-render a screen. Stops a handler that keeps
+/// redirecting to itself before it blows the stack or spins forever.
+pub const MAX_CALLBACKS_PER_UPDATE: usize = 1000;
+
+tokio::task_local! {
+    static CALLBACKS_PARSED: Cell<usize>;
+}
+
 /// Use this as callback data if you're 100% sure that the callback data will never be used
 pub const DONT_CARE: &str = "dontcare";
 pub const BASE_REFERRAL_SHARE: f64 = 0.15;
@@ -70,6 +83,10 @@ pub const STARS_PER_USD: u32 = 77; // 77 stars = $1
 pub const NOTIFICATION_LIMIT_5M: usize = 20;
 pub const NOTIFICATION_LIMIT_1H: usize = 150;
 pub const NOTIFICATION_LIMIT_1D: usize = 1000;
+/// Minimum interval between two "notification limit reached" notices in the same chat
+pub const NOTIFICATION_LIMIT_NOTICE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Minimum interval between two "temporarily unavailable" replies in the same chat
+pub const DATABASE_UNAVAILABLE_NOTICE_INTERVAL: Duration = Duration::from_secs(60);
 
 pub fn stars_to_usd(stars: u32) -> f64 {
     stars as f64 / STARS_PER_USD as f64
@@ -95,6 +112,12 @@ pub struct BotData {
     messages_sent_in_1h: Arc<DashMap<ChatId, AtomicUsize>>,
     messages_sent_in_1d: Arc<DashMap<ChatId, AtomicUsize>>,
     last_message_limit_notification: DashMap<ChatId, Instant>,
+    last_database_unavailable_notice: DashMap<ChatId, Instant>,
+    notification_limit_notices: PersistentCachedStore<ChatId, bool>,
+    maintenance_mode: AtomicBool,
+    /// Persisted copy of `maintenance_mode`, so it survives restarts
+    maintenance_mode_store: PersistentCachedStore<(), bool>,
+    suppressed_in_maintenance_mode: AtomicUsize,
     chat_permission_levels: PersistentCachedStore<ChatId, ChatPermissionLevel>,
     referred_by: PersistentCachedStore<UserId, UserId>,
     referral_balance: PersistentCachedStore<UserId, HashMap<AccountId, StringifiedBalance>>,
@@ -156,6 +179,21 @@ impl BotData {
             }
         });
 
+        let notification_limit_notices = PersistentCachedStore::new(
+            db.clone(),
+            &format!("bot{bot_id}_notification_limit_notices"),
+        )
+        .await?;
+        // Checked for every dropped notification, so keep all of them in memory
+        notification_limit_notices.values().await?;
+        let maintenance_mode_store =
+            PersistentCachedStore::new(db.clone(), &format!("bot{bot_id}_maintenance_mode"))
+                .await?;
+        let maintenance_mode = maintenance_mode_store.get(&()).await.unwrap_or(false);
+        if maintenance_mode {
+            log::warn!("Bot {bot_id} is starting in maintenance mode");
+        }
+
         Ok(Self {
             bot,
             bot_type,
@@ -200,6 +238,11 @@ impl BotData {
             messages_sent_in_1h,
             messages_sent_in_1d,
             last_message_limit_notification: DashMap::new(),
+            last_database_unavailable_notice: DashMap::new(),
+            notification_limit_notices,
+            maintenance_mode: AtomicBool::new(maintenance_mode),
+            maintenance_mode_store,
+            suppressed_in_maintenance_mode: AtomicUsize::new(0),
             chat_permission_levels: PersistentCachedStore::new(
                 db.clone(),
                 &format!("bot{bot_id}_chat_permission_levels"),
@@ -453,8 +496,9 @@ impl BotData {
         tokio::spawn(async move {
             while let Some(msg) = msg_receiver.recv().await {
                 let xeon = Arc::clone(&xeon);
-                tokio::spawn(async move {
+                tokio::spawn(CALLBACKS_PARSED.scope(Cell::new(0), async move {
                     let text = msg.text().or(msg.caption()).unwrap_or_default();
+                    let mut database_unavailable = false;
                     for module in xeon.bot_modules().await.iter() {
                         let bot = xeon.bot(&me).unwrap();
                         let result = if text.starts_with("/start") {
@@ -576,9 +620,16 @@ impl BotData {
                                 module.name(),
                                 err
                             );
+                            database_unavailable |= is_database_error(&err);
+                        }
+                    }
+                    // Only in DMs, group messages fail for everyone during an outage
+                    if database_unavailable && msg.chat.id.is_user() {
+                        if let Some(bot) = xeon.bot(&me) {
+                            bot.notify_database_unavailable(msg.chat.id).await;
                         }
                     }
-                });
+                }));
             }
         });
 
@@ -586,10 +637,11 @@ impl BotData {
         tokio::spawn(async move {
             while let Some(callback_query) = callback_query_receiver.recv().await {
                 let xeon = Arc::clone(&xeon);
-                tokio::spawn(async move {
+                tokio::spawn(CALLBACKS_PARSED.scope(Cell::new(0), async move {
                     if let (Some(data), Some(message)) =
                         (callback_query.data, callback_query.message)
                     {
+                        let mut database_unavailable = false;
                         for module in xeon.bot_modules().await.iter() {
                             let bot = xeon.bot(&me).unwrap();
                             let context = TgCallbackContext::new(
@@ -612,13 +664,19 @@ impl BotData {
                                     module.name(),
                                     err
                                 );
+                                database_unavailable |= is_database_error(&err);
                             }
                             if let Some(query) = query {
                                 query.answer_callback_query(&xeon).await;
                             }
                         }
+                        if database_unavailable {
+                            if let Some(bot) = xeon.bot(&me) {
+                                bot.notify_database_unavailable(message.chat().id).await;
+                            }
+                        }
                     }
-                });
+                }));
             }
         });
 
@@ -854,6 +912,28 @@ impl BotData {
         )
     }
 
+    /// Tells the user to try again later if an action failed because MongoDB
+    /// is unavailable, instead of leaving them without a response. At most once
+    /// per `DATABASE_UNAVAILABLE_NOTICE_INTERVAL` per chat.
+    async fn notify_database_unavailable(&self, chat_id: ChatId) {
+        if let Some(last_notice) = self.last_database_unavailable_notice.get(&chat_id) {
+            if last_notice.elapsed() < DATABASE_UNAVAILABLE_NOTICE_INTERVAL {
+                return;
+            }
+        }
+        self.last_database_unavailable_notice
+            .insert(chat_id, Instant::now());
+        if let Err(err) = self
+            .send_text_message_without_reply_markup(
+                chat_id,
+                "⚠️ Settings are temporarily unavailable, please try again in a minute".to_string(),
+            )
+            .await
+        {
+            warn!("Error sending database unavailable message: {err:?}");
+        }
+    }
+
     pub async fn send_text_message_without_reply_markup(
         &self,
         chat_id: ChatId,
@@ -916,6 +996,14 @@ impl BotData {
             .expect("Error creating migration data")
     }
 
+    /// Creates a `/start` payload that opens `data` when the user follows
+    /// `https://t.me/{bot_username}?start=cmd-{payload}`. Unlike callback data,
+    /// it's stored globally and never expires, so links can be shared outside of Telegram.
+    pub async fn to_deep_link_payload(&self, data: &TgCommand) -> Result<String, anyhow::Error> {
+        let data = serde_json::to_string(data)?;
+        self.create_global_hash_reference(data).await
+    }
+
     pub async fn get_hash_reference(&self, b58: &str) -> Option<String> {
         let hash: CryptoHash = b58.parse().ok()?;
         self.callback_data_cache.get(&hash.to_string()).await
@@ -947,6 +1035,14 @@ impl BotData {
         Ok(serde_json::from_str(&data)?)
     }
 
+    pub async fn parse_deep_link_payload(&self, b58: &str) -> Result<TgCommand, anyhow::Error> {
+        let data = self
+            .get_global_hash_reference(b58)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("Deep link cannot be restored"))?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
     pub async fn parse_migration_data(&self, b58: &str) -> Result<MigrationData, anyhow::Error> {
         let data = self
             .get_global_hash_reference(b58)
@@ -1005,57 +1101,33 @@ impl BotData {
         text: impl Into<String>,
         reply_markup: impl Into<ReplyMarkup>,
         attachment: Attachment,
+    ) -> Result<Message, anyhow::Error> {
+        self.send_with_sound(chat_id, text, reply_markup, attachment, true)
+            .await
+    }
+
+    /// Same as `send`, but if `sound` is `false`, the message is delivered silently
+    /// (`disable_notification`), so it doesn't buzz the recipients' phones
+    pub async fn send_with_sound(
+        &self,
+        chat_id: impl Into<NotificationDestination>,
+        text: impl Into<String>,
+        reply_markup: impl Into<ReplyMarkup>,
+        attachment: Attachment,
+        sound: bool,
     ) -> Result<Message, anyhow::Error> {
         let text = text.into();
         let chat_id = chat_id.into();
 
         Ok(match attachment {
             Attachment::None => {
-                if text.len() < 4096 {
-                    attach_thread_id!(
-                        self.bot.send_message(chat_id.chat_id(), text.clone()),
-                        chat_id
-                    )
-                    .parse_mode(ParseMode::MarkdownV2)
-                    .reply_markup(reply_markup)
-                    .link_preview_options(LinkPreviewOptions {
-                        is_disabled: true,
-                        url: None,
-                        prefer_small_media: false,
-                        prefer_large_media: false,
-                        show_above_text: false,
-                    })
-                    .await
-                    .inspect_err(log_parse_error(text))?
-                } else {
-                    attach_thread_id!(
-                        self.bot.send_document(
-                            chat_id.chat_id(),
-                            InputFile::memory({
-                                const CHARS: [char; 19] = [
-                                    '\\', '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+',
-                                    '-', '=', '|', '{', '}', '.', '!',
-                                ];
-
-                                let mut text = text;
-                                for c in CHARS {
-                                    text = text.replace(&format!("\\{c}"), &c.to_string());
-                                }
-                                text
-                            })
-                            .file_name("message.txt"),
-                        ),
-                        chat_id
-                    )
-                    .caption("The response was too long, so it was sent as a file\\.")
-                    .parse_mode(ParseMode::MarkdownV2)
-                    .reply_markup(reply_markup)
+                self.send_text_or_file(chat_id, text, reply_markup.into(), sound)
                     .await?
-                }
             }
             Attachment::PhotoUrl(url) => attach_thread_id!(
                 self.bot.send_photo(chat_id.chat_id(), InputFile::url(url)),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
@@ -1065,7 +1137,8 @@ impl BotData {
             Attachment::PhotoFileId(file_id) => attach_thread_id!(
                 self.bot
                     .send_photo(chat_id.chat_id(), InputFile::file_id(file_id)),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
@@ -1075,7 +1148,8 @@ impl BotData {
             Attachment::PhotoBytes(bytes) => attach_thread_id!(
                 self.bot
                     .send_photo(chat_id.chat_id(), InputFile::memory(bytes)),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
@@ -1085,7 +1159,8 @@ impl BotData {
             Attachment::AnimationUrl(url) => attach_thread_id!(
                 self.bot
                     .send_animation(chat_id.chat_id(), InputFile::url(url)),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
@@ -1095,7 +1170,8 @@ impl BotData {
             Attachment::AnimationFileId(file_id) => attach_thread_id!(
                 self.bot
                     .send_animation(chat_id.chat_id(), InputFile::file_id(file_id)),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
@@ -1104,7 +1180,8 @@ impl BotData {
             .inspect_err(log_parse_error(text))?,
             Attachment::AudioUrl(url) => attach_thread_id!(
                 self.bot.send_audio(chat_id.chat_id(), InputFile::url(url)),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
@@ -1114,7 +1191,8 @@ impl BotData {
             Attachment::AudioFileId(file_id) => attach_thread_id!(
                 self.bot
                     .send_audio(chat_id.chat_id(), InputFile::file_id(file_id)),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
@@ -1123,7 +1201,8 @@ impl BotData {
             .inspect_err(log_parse_error(text))?,
             Attachment::VideoUrl(url) => attach_thread_id!(
                 self.bot.send_video(chat_id.chat_id(), InputFile::url(url)),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
@@ -1133,7 +1212,8 @@ impl BotData {
             Attachment::VideoFileId(file_id) => attach_thread_id!(
                 self.bot
                     .send_video(chat_id.chat_id(), InputFile::file_id(file_id)),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
@@ -1143,7 +1223,8 @@ impl BotData {
             Attachment::DocumentUrl(url, file_name) => attach_thread_id!(
                 self.bot
                     .send_document(chat_id.chat_id(), InputFile::url(url).file_name(file_name)),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
@@ -1155,7 +1236,8 @@ impl BotData {
                     chat_id.chat_id(),
                     InputFile::memory(content).file_name(file_name)
                 ),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
@@ -1167,19 +1249,107 @@ impl BotData {
                     chat_id.chat_id(),
                     InputFile::file_id(file_id).file_name(file_name)
                 ),
-                chat_id
+                chat_id,
+                !sound
             )
             .caption(text.clone())
             .parse_mode(ParseMode::MarkdownV2)
             .reply_markup(reply_markup)
             .await
             .inspect_err(log_parse_error(text))?,
+            Attachment::StickerFileId(file_id) => {
+                if text.is_empty() {
+                    attach_thread_id!(
+                        self.bot
+                            .send_sticker(chat_id.chat_id(), InputFile::file_id(file_id)),
+                        chat_id,
+                        !sound
+                    )
+                    .reply_markup(reply_markup)
+                    .await?
+                } else {
+                    attach_thread_id!(
+                        self.bot
+                            .send_sticker(chat_id.chat_id(), InputFile::file_id(file_id)),
+                        chat_id,
+                        !sound
+                    )
+                    .await?;
+                    self.send_text_or_file(chat_id, text, reply_markup.into(), sound)
+                        .await?
+                }
+            }
+        })
+    }
+
+    /// Sends `text` as a message, or as a file if it's too long for one
+    async fn send_text_or_file(
+        &self,
+        chat_id: NotificationDestination,
+        text: String,
+        reply_markup: ReplyMarkup,
+        sound: bool,
+    ) -> Result<Message, anyhow::Error> {
+        Ok(if text.len() < 4096 {
+            attach_thread_id!(
+                self.bot.send_message(chat_id.chat_id(), text.clone()),
+                chat_id,
+                !sound
+            )
+            .parse_mode(ParseMode::MarkdownV2)
+            .reply_markup(reply_markup)
+            .link_preview_options(LinkPreviewOptions {
+                is_disabled: true,
+                url: None,
+                prefer_small_media: false,
+                prefer_large_media: false,
+                show_above_text: false,
+            })
+            .await
+            .inspect_err(log_parse_error(text))?
+        } else {
+            attach_thread_id!(
+                self.bot.send_document(
+                    chat_id.chat_id(),
+                    InputFile::memory({
+                        const CHARS: [char; 19] = [
+                            '\\', '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=',
+                            '|', '{', '}', '.', '!',
+                        ];
+
+                        let mut text = text;
+                        for c in CHARS {
+                            text = text.replace(&format!("\\{c}"), &c.to_string());
+                        }
+                        text
+                    })
+                    .file_name("message.txt"),
+                ),
+                chat_id,
+                !sound
+            )
+            .caption("The response was too long, so it was sent as a file\\.")
+            .parse_mode(ParseMode::MarkdownV2)
+            .reply_markup(reply_markup)
+            .await?
         })
     }
 
     pub async fn reached_notification_limit(&self, chat_id: ChatId) -> bool {
-        if let Some(messages) = self.messages_sent_in_5m.get(&chat_id) {
-            let messages = messages.fetch_add(1, Ordering::Relaxed);
+        if simulation_target().is_some_and(|target| target != chat_id) {
+            return true;
+        }
+        if self.is_in_maintenance_mode() {
+            self.suppressed_in_maintenance_mode
+                .fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+        // Don't hold the map guard while sending the notice
+        let messages = self
+            .messages_sent_in_5m
+            .get(&chat_id)
+            .map(|messages| messages.fetch_add(1, Ordering::Relaxed));
+        if let Some(messages) = messages {
             if messages > NOTIFICATION_LIMIT_5M {
                 self.send_message_limit_message(
                     chat_id,
@@ -1194,8 +1364,11 @@ impl BotData {
             self.messages_sent_in_5m
                 .insert(chat_id, AtomicUsize::new(1));
         }
-        if let Some(messages) = self.messages_sent_in_1h.get(&chat_id) {
-            let messages = messages.fetch_add(1, Ordering::Relaxed);
+        let messages = self
+            .messages_sent_in_1h
+            .get(&chat_id)
+            .map(|messages| messages.fetch_add(1, Ordering::Relaxed));
+        if let Some(messages) = messages {
             if messages > NOTIFICATION_LIMIT_1H {
                 self.send_message_limit_message(
                     chat_id,
@@ -1210,8 +1383,11 @@ impl BotData {
             self.messages_sent_in_1h
                 .insert(chat_id, AtomicUsize::new(1));
         }
-        if let Some(messages) = self.messages_sent_in_1d.get(&chat_id) {
-            let messages = messages.fetch_add(1, Ordering::Relaxed);
+        let messages = self
+            .messages_sent_in_1d
+            .get(&chat_id)
+            .map(|messages| messages.fetch_add(1, Ordering::Relaxed));
+        if let Some(messages) = messages {
             if messages > NOTIFICATION_LIMIT_1D {
                 self.send_message_limit_message(
                     chat_id,
@@ -1238,10 +1414,13 @@ impl BotData {
         messages: usize,
     ) {
         if let Some(last_notification) = self.last_message_limit_notification.get(&chat_id) {
-            if last_notification.elapsed() < duration {
+            if last_notification.elapsed() < duration.max(NOTIFICATION_LIMIT_NOTICE_INTERVAL) {
                 return;
             }
         }
+        if !self.notification_limit_notice_enabled(chat_id).await {
+            return;
+        }
         self.last_message_limit_notification
             .insert(chat_id, Instant::now());
         let bot = self.bot.clone();
@@ -1249,7 +1428,7 @@ impl BotData {
             tokio::time::sleep(Duration::from_secs(10)).await; // Make sure this is the last message after all notifications are sent
             if let Err(err) = bot
                 .send_message(chat_id, format!(
-                    "You have reached the notification limit of {messages}/{limit} messages in {}\\.\nPlease fix your settings\\.",
+                    "⚠️ You have reached the notification limit of {messages}/{limit} messages in {}, some notifications were not shown\\.\nPlease raise your thresholds to get fewer notifications\\.",
                     markdown::escape(&format_duration(duration))
                 ))
                 .parse_mode(ParseMode::MarkdownV2)
@@ -1266,6 +1445,48 @@ impl BotData {
         });
     }
 
+    /// When enabled, `reached_notification_limit` returns true for every chat, so
+    /// notifications from modules that check it before sending are dropped without
+    /// touching any settings. Other messages, like replies to commands, are still sent.
+    /// Persisted per bot, so it stays on after a restart.
+    pub fn is_in_maintenance_mode(&self) -> bool {
+        self.maintenance_mode.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of notifications suppressed since maintenance mode was enabled.
+    /// The count is kept in memory only and starts from 0 after a restart.
+    pub async fn set_maintenance_mode(&self, enabled: bool) -> Result<usize, anyhow::Error> {
+        self.maintenance_mode_store
+            .insert_or_update((), enabled)
+            .await?;
+        let was_enabled = self.maintenance_mode.swap(enabled, Ordering::Relaxed);
+        if enabled && !was_enabled {
+            self.suppressed_in_maintenance_mode
+                .store(0, Ordering::Relaxed);
+        }
+        Ok(self.suppressed_in_maintenance_mode.load(Ordering::Relaxed))
+    }
+
+    /// Whether the chat gets a notice when notifications are dropped because of the limit.
+    /// Off unless the chat enabled it. The notice is sent directly and doesn't count towards the limit.
+    pub async fn notification_limit_notice_enabled(&self, chat_id: ChatId) -> bool {
+        self.notification_limit_notices
+            .get(&chat_id)
+            .await
+            .unwrap_or(false)
+    }
+
+    pub async fn set_notification_limit_notice_enabled(
+        &self,
+        chat_id: ChatId,
+        enabled: bool,
+    ) -> Result<(), anyhow::Error> {
+        self.notification_limit_notices
+            .insert_or_update(chat_id, enabled)
+            .await?;
+        Ok(())
+    }
+
     pub async fn get_chat_permission_level(&self, chat_id: ChatId) -> ChatPermissionLevel {
         self.chat_permission_levels
             .get(&chat_id)
@@ -1348,6 +1569,19 @@ impl<'a> TgCallbackContext<'a> {
         if self.data == DONT_CARE {
             return Err(anyhow::anyhow!("Tried to parse DONT_CARE callback data"));
         }
+        // Outside of update handling (e.g. in notification tasks) there's no counter
+        if let Ok(parsed) = CALLBACKS_PARSED.try_with(|parsed| {
+            parsed.set(parsed.get() + 1);
+            parsed.get()
+        }) {
+            if parsed > MAX_CALLBACKS_PER_UPDATE {
+                log::warn!(
+                    "Parsed more than {MAX_CALLBACKS_PER_UPDATE} callbacks while handling one update, probably a redirect loop. Last callback data: {}",
+                    self.data
+                );
+                return Err(anyhow::anyhow!("Too many nested callbacks"));
+            }
+        }
         self.bot.parse_callback_data(self.data).await
     }
 
@@ -1482,6 +1716,8 @@ pub enum Attachment {
     DocumentUrl(Url, String),
     DocumentText(String, String),
     DocumentFileId(String, String),
+    /// Stickers can't have a caption, so the text is sent as a separate message
+    StickerFileId(String),
 }
 
 pub struct MustAnswerCallbackQuery {
@@ -1513,6 +1749,11 @@ impl Drop for MustAnswerCallbackQuery {
     }
 }
 
+fn is_database_error(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|e| e.downcast_ref::<mongodb::error::Error>().is_some())
+}
+
 fn log_parse_error(text: impl Into<String>) -> impl FnOnce(&RequestError) {
     let text = text.into();
     move |err| {
@@ -106,6 +106,9 @@ where
         if let Some(value) = self.cache.get(key).as_deref() {
             return Some(value.clone());
         }
+        if self.cached_all.load(Ordering::Relaxed) {
+            return None;
+        }
         match bson::to_bson(key) {
             Ok(key_bson) => self
                 .db
@@ -241,11 +244,16 @@ where
     }
 
     pub async fn remove(&self, key: &K) -> Result<Option<V>, anyhow::Error> {
-        let removed = self.cache.remove(key);
+        let removed = self.cache.remove(key).map(|(_, value)| value);
         if self.cached_all.load(Ordering::Relaxed) && removed.is_none() {
             return Ok(None);
         }
-        if let Some(value) = self.get(key).await {
+        // With `cached_all`, `get` only looks at the cache, which no longer has the key
+        let value = match removed {
+            Some(value) => Some(value),
+            None => self.get(key).await,
+        };
+        if let Some(value) = value {
             let key_bson = bson::to_bson(key)?;
             self.db.delete_one(bson::doc! { "key": key_bson }).await?;
             Ok(Some(value))
@@ -14,6 +14,12 @@ pub const NEAR_DECIMALS: u32 = 24;
 pub const WRAP_NEAR: &str = "wrap.near";
 pub const USDT_TOKEN: &str = "usdt.tether-token.near";
 pub const USDT_DECIMALS: u32 = 6;
+/// Highest number of decimal places that `format_usd_amount_with_precision` will render
+pub const MAX_USD_DECIMALS: usize = 24;
+/// Highest `decimals` value expected from `ft_metadata`. Tokens with more are logged but
+/// still formatted with their real decimals, and values above 38 are shown in raw units
+/// since they overflow `10u128.pow`
+pub const MAX_FT_DECIMALS: u32 = 24;
 
 pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
 pub const SOL_DECIMALS: u32 = 9;
@@ -91,15 +97,12 @@ pub async fn format_tokens(
             if let Some(xeon) = include_price {
                 if amount != 0 {
                     if let Some(price) = xeon.get_price_if_known(token).await {
-                        if price != 0f64 {
-                            format!(
+                        match 10u128.checked_pow(metadata.decimals) {
+                            Some(one_token) if price != 0f64 => format!(
                                 " ({})",
-                                format_usd_amount(
-                                    (amount as f64 / 10u128.pow(metadata.decimals) as f64) * price,
-                                )
-                            )
-                        } else {
-                            "".to_string()
+                                format_usd_amount((amount as f64 / one_token as f64) * price)
+                            ),
+                            _ => "".to_string(),
                         }
                     } else {
                         "".to_string()
@@ -128,8 +131,16 @@ pub async fn get_ft_metadata(token: &AccountId) -> Result<FungibleTokenMetadata,
             decimals: NEAR_DECIMALS,
         })
     } else {
-        view_cached_1h::<_, FungibleTokenMetadata>(token, "ft_metadata", serde_json::json!({}))
-            .await
+        let metadata =
+            view_cached_1h::<_, FungibleTokenMetadata>(token, "ft_metadata", serde_json::json!({}))
+                .await?;
+        if metadata.decimals > MAX_FT_DECIMALS {
+            log::warn!(
+                "Token {token} reports {} decimals, more than the expected {MAX_FT_DECIMALS}",
+                metadata.decimals
+            );
+        }
+        Ok(metadata)
     }
 }
 
@@ -148,6 +159,10 @@ pub fn format_token_amount(amount: Balance, decimals: u32, symbol: &str) -> Stri
     if decimals == 0 {
         return format!("{amount} {symbol}");
     }
+    if 10u128.checked_pow(decimals).is_none() {
+        // Can't be represented, show raw units instead of garbage
+        return format!("{amount} raw units of {symbol}");
+    }
     if amount == 0 {
         return format!("0 {symbol}");
     }
@@ -214,10 +229,56 @@ fn format_number(num: f64, precision: usize) -> String {
 }
 
 pub fn format_usd_amount(amount: f64) -> String {
-    format_number(
-        amount,
-        (3 - amount.log10().clamp(-20.0, 3.0) as isize) as usize,
-    )
+    format_usd_amount_with_precision(amount, None)
+}
+
+/// Formats a USD amount with a fixed number of decimal places, or picks the
+/// precision automatically based on the magnitude of the amount if `decimals` is `None`.
+pub fn format_usd_amount_with_precision(amount: f64, decimals: Option<usize>) -> String {
+    if decimals.is_none() {
+        if let Some(formatted) = format_usd_amount_subscript_zeros(amount) {
+            return formatted;
+        }
+    }
+    let precision = decimals
+        .unwrap_or_else(|| (3 - amount.log10().clamp(-20.0, 3.0) as isize) as usize)
+        .min(MAX_USD_DECIMALS);
+    format_number(amount, precision)
+}
+
+/// Number of leading fractional zeros from which amounts are rendered in subscript notation
+pub const SUBSCRIPT_ZEROS_THRESHOLD: usize = 4;
+
+/// Renders tiny amounts DexScreener-style, e.g. 0.00000000042 as `$0.0₉42`, where
+/// the subscript is the number of zeros after the decimal point. Returns `None`
+/// for amounts that have less than `SUBSCRIPT_ZEROS_THRESHOLD` leading zeros.
+pub fn format_usd_amount_subscript_zeros(amount: f64) -> Option<String> {
+    const SUBSCRIPT_DIGITS: [char; 10] = ['₀', '₁', '₂', '₃', '₄', '₅', '₆', '₇', '₈', '₉'];
+
+    if !(amount > 0.0 && amount < 1.0) {
+        return None;
+    }
+    // Scientific notation rounds the significant digits first, so 0.000099999 doesn't
+    // end up with one zero too many
+    let formatted = format!("{amount:.3e}");
+    let (mantissa, exponent) = formatted.split_once('e')?;
+    let exponent: i32 = exponent.parse().ok()?;
+    if exponent >= 0 {
+        return None;
+    }
+    let leading_zeros = (-exponent - 1) as usize;
+    if leading_zeros < SUBSCRIPT_ZEROS_THRESHOLD {
+        return None;
+    }
+    let digits = mantissa.replace('.', "");
+    let digits = digits.trim_end_matches('0');
+    let subscript: String = leading_zeros
+        .to_string()
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|digit| SUBSCRIPT_DIGITS[digit as usize])
+        .collect();
+    Some(format!("$0.0{subscript}{digits}"))
 }
 
 pub async fn format_account_id(account_id: &AccountId) -> String {
@@ -333,3 +394,67 @@ pub async fn get_memecooking_finalized_info(
     )
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_amount_without_decimals() {
+        assert_eq!(format_token_amount(0, 0, "X"), "0 X");
+        assert_eq!(format_token_amount(42, 0, "X"), "42 X");
+    }
+
+    #[test]
+    fn token_amount_with_24_decimals() {
+        assert_eq!(
+            format_token_amount(1_500_000_000_000_000_000_000_000, 24, "NEAR"),
+            "1.5 NEAR"
+        );
+        assert_eq!(
+            format_token_amount(1_000_000_000_000_000_000, 24, "NEAR"),
+            "0.000001 NEAR"
+        );
+    }
+
+    #[test]
+    fn token_amount_with_unsupported_decimals() {
+        assert_eq!(format_token_amount(5, 39, "X"), "5 raw units of X");
+        assert_eq!(format_token_amount(5, u32::MAX, "X"), "5 raw units of X");
+    }
+
+    #[test]
+    fn subscript_zeros_threshold() {
+        assert_eq!(format_usd_amount_subscript_zeros(0.0001), None);
+        assert_eq!(format_usd_amount(0.0001), "$0.0001000");
+        assert_eq!(
+            format_usd_amount_subscript_zeros(0.00001).as_deref(),
+            Some("$0.0₄1")
+        );
+        assert_eq!(format_usd_amount(0.00009), "$0.0₄9");
+    }
+
+    /// 0.000099999 rounds up to 0.0001, so it must not get 4 leading zeros
+    #[test]
+    fn subscript_zeros_rounding_edge() {
+        assert_eq!(format_usd_amount_subscript_zeros(0.000099999), None);
+        assert_eq!(format_usd_amount(0.000099999), "$0.0001000");
+    }
+
+    #[test]
+    fn subscript_zeros() {
+        assert_eq!(format_usd_amount(0.00000000042), "$0.0₉42");
+        assert_eq!(format_usd_amount(1e-20), "$0.0₁₉1");
+    }
+
+    #[test]
+    fn subscript_zeros_ignores_regular_amounts() {
+        assert_eq!(format_usd_amount_subscript_zeros(0.5), None);
+        assert_eq!(format_usd_amount_subscript_zeros(0.0), None);
+        assert_eq!(format_usd_amount_subscript_zeros(1.5), None);
+        assert_eq!(
+            format_usd_amount_with_precision(0.00000000042, Some(2)),
+            "$0.00"
+        );
+    }
+}
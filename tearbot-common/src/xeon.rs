@@ -1,3 +1,6 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
 use std::{collections::HashMap, sync::Arc};
 
 use crate::bot_commands::PoolId;
@@ -5,7 +8,7 @@ use crate::tgbot::NotificationDestination;
 use crate::utils::store::PersistentCachedStore;
 use crate::{
     bot_commands::{MessageCommand, PaymentReference},
-    indexer_events::IndexerEventHandler,
+    indexer_events::{IndexerEvent, IndexerEventHandler},
     tgbot::{BotData, MustAnswerCallbackQuery, TgCallbackContext},
     utils::{requests::get_not_cached, tokens::WRAP_NEAR},
 };
@@ -23,7 +26,17 @@ use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use teloxide::prelude::{ChatId, Message, UserId};
 use teloxide::types::{InlineQuery, InlineQueryResult};
-use tokio::sync::{RwLock, RwLockReadGuard};
+use tokio::sync::{Notify, RwLock, RwLockReadGuard};
+
+tokio::task_local! {
+    /// Set while a simulated event is handled, the only chat that may receive notifications
+    static SIMULATION_TARGET: ChatId;
+}
+
+/// Chat that notifications are limited to if the current task handles a simulated event
+pub fn simulation_target() -> Option<ChatId> {
+    SIMULATION_TARGET.try_with(|target| *target).ok()
+}
 
 pub struct Xeon {
     state: Arc<XeonState>,
@@ -62,8 +75,25 @@ pub struct XeonState {
     indexer_event_handlers: RwLock<Vec<Arc<dyn IndexerEventHandler>>>,
     db: Database,
     prices: Arc<RwLock<HashMap<AccountId, TokenInfo>>>,
+    prices_updated_at: Arc<RwLock<Option<std::time::Instant>>>,
     spamlist: Arc<RwLock<Vec<AccountId>>>,
     airdrop_state: PersistentCachedStore<UserId, AirdropState>,
+    pending_notification_tasks: Arc<AtomicUsize>,
+    notification_task_finished: Arc<Notify>,
+    shutting_down: AtomicBool,
+}
+
+/// Decrements the pending notification task counter when the task finishes or panics
+struct NotificationTaskGuard {
+    pending: Arc<AtomicUsize>,
+    finished: Arc<Notify>,
+}
+
+impl Drop for NotificationTaskGuard {
+    fn drop(&mut self) {
+        self.pending.fetch_sub(1, Ordering::SeqCst);
+        self.finished.notify_waiters();
+    }
 }
 
 pub const TRADING_POINTS_DAILY_CAP: f64 = 10.0;
@@ -142,6 +172,8 @@ impl XeonState {
     pub async fn new(db: Database) -> Self {
         let prices = Arc::new(RwLock::new(HashMap::new()));
         let prices_clone = Arc::clone(&prices);
+        let prices_updated_at = Arc::new(RwLock::new(None));
+        let prices_updated_at_clone = Arc::clone(&prices_updated_at);
         let spamlist = Arc::new(RwLock::new(Vec::new()));
         let spamlist_clone = Arc::clone(&spamlist);
         tokio::spawn(async move {
@@ -160,6 +192,7 @@ impl XeonState {
                                 .unwrap(),
                         );
                         *prices_clone.write().await = new_prices;
+                        *prices_updated_at_clone.write().await = Some(std::time::Instant::now());
                     }
                 } else {
                     log::warn!("Failed to get prices")
@@ -191,8 +224,12 @@ impl XeonState {
             indexer_event_handlers: RwLock::new(Vec::new()),
             db,
             prices,
+            prices_updated_at,
             spamlist,
             airdrop_state,
+            pending_notification_tasks: Arc::new(AtomicUsize::new(0)),
+            notification_task_finished: Arc::new(Notify::new()),
+            shutting_down: AtomicBool::new(false),
         }
     }
 
@@ -234,10 +271,120 @@ impl XeonState {
             .push(handler.into());
     }
 
+    /// Passes the event to all indexer event handlers. Besides the event stream, this is
+    /// used to inject synthetic events for end-to-end testing.
+    pub async fn dispatch_indexer_event(&self, event: &IndexerEvent) {
+        if self.is_shutting_down() {
+            return;
+        }
+        for handler in self.indexer_event_handlers().await.iter() {
+            let now = std::time::Instant::now();
+            log::debug!(target: "indexer_events", "Handling event {event:?}");
+            if let Err(err) = handler.handle_event(event).await {
+                log::error!("Failed to handle event {event:?}: {err:?}");
+            }
+            log::debug!(target: "indexer_events", "Event Handled");
+            let elapsed = now.elapsed();
+            const HANDLER_WARNING_THRESHOLD: Duration = Duration::from_millis(10);
+            if elapsed > HANDLER_WARNING_THRESHOLD {
+                log::warn!(
+                    "Event handler took more than {HANDLER_WARNING_THRESHOLD:?} to process event {event:?}: {elapsed:?}"
+                );
+            }
+        }
+    }
+
+    /// Runs a synthetic event through the handlers that opted in with
+    /// `IndexerEventHandler::supports_simulation`. While they handle it,
+    /// `reached_notification_limit` drops notifications to any chat except `target`.
+    /// Returns the number of handlers that received the event.
+    pub async fn dispatch_simulated_indexer_event(
+        &self,
+        event: &IndexerEvent,
+        target: ChatId,
+    ) -> usize {
+        let handlers = self
+            .indexer_event_handlers()
+            .await
+            .iter()
+            .filter(|handler| handler.supports_simulation())
+            .cloned()
+            .collect::<Vec<_>>();
+        for handler in handlers.iter() {
+            if let Err(err) = SIMULATION_TARGET
+                .scope(target, handler.handle_event(event))
+                .await
+            {
+                log::error!("Failed to handle simulated event {event:?}: {err:?}");
+            }
+        }
+        handlers.len()
+    }
+
     pub fn db(&self) -> Database {
         self.db.clone()
     }
 
+    /// Spawns a task that sends notifications. Unlike plain `tokio::spawn`, these
+    /// tasks are awaited on shutdown by `wait_for_notification_tasks`. Tasks spawned
+    /// after `begin_shutdown` are dropped.
+    ///
+    /// Only modules that use this are tracked. The private ft-buybot module still sends
+    /// from plain `tokio::spawn` tasks, which are neither counted nor waited for, so its
+    /// in-flight notifications can still be lost on shutdown.
+    pub fn spawn_notification_task(&self, task: impl Future<Output = ()> + Send + 'static) {
+        // Count the task before checking the flag, so that `wait_for_notification_tasks`
+        // can't miss a task that was accepted right before shutdown
+        self.pending_notification_tasks
+            .fetch_add(1, Ordering::SeqCst);
+        let guard = NotificationTaskGuard {
+            pending: Arc::clone(&self.pending_notification_tasks),
+            finished: Arc::clone(&self.notification_task_finished),
+        };
+        if self.is_shutting_down() {
+            log::debug!("Shutting down, dropping notification task");
+            return;
+        }
+        let simulation_target = simulation_target();
+        tokio::spawn(async move {
+            let _guard = guard;
+            match simulation_target {
+                Some(target) => SIMULATION_TARGET.scope(target, task).await,
+                None => task.await,
+            }
+        });
+    }
+
+    /// Stops dispatching indexer events and accepting new notification tasks, so that
+    /// `wait_for_notification_tasks` only waits for the ones already running
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    pub fn pending_notification_tasks(&self) -> usize {
+        self.pending_notification_tasks.load(Ordering::SeqCst)
+    }
+
+    /// Waits until all tasks spawned with `spawn_notification_task` have finished, or
+    /// `timeout` has passed. Returns the number of tasks that were still running.
+    pub async fn wait_for_notification_tasks(&self, timeout: Duration) -> usize {
+        let _ = tokio::time::timeout(timeout, async {
+            loop {
+                let finished = self.notification_task_finished.notified();
+                if self.pending_notification_tasks() == 0 {
+                    break;
+                }
+                finished.await;
+            }
+        })
+        .await;
+        self.pending_notification_tasks()
+    }
+
     pub async fn get_price(&self, account_id: &AccountId) -> f64 {
         self.get_price_if_known(account_id)
             .await
@@ -266,10 +413,22 @@ impl XeonState {
             .map(|info| info.price_usd_raw / 1e6) // 6 is decimals of usdt
     }
 
+    /// Not cached beyond the shared token list, which is re-fetched from prices.intear.tech
+    /// every 5 seconds, so supply and market cap (including after mints and burns) normally
+    /// lag the price indexer by a few seconds. If a fetch fails, the previous list is kept,
+    /// so the data can get older than that. Use `prices_age` to check.
     pub async fn get_token_info(&self, account_id: &AccountId) -> Option<TokenInfo> {
         self.prices.read().await.get(account_id).cloned()
     }
 
+    /// Time since the token list was last fetched successfully, or `None` if it never was
+    pub async fn prices_age(&self) -> Option<Duration> {
+        self.prices_updated_at
+            .read()
+            .await
+            .map(|updated_at| updated_at.elapsed())
+    }
+
     pub async fn get_token_list(&self) -> Vec<TokenInfo> {
         self.prices.read().await.values().cloned().collect()
     }
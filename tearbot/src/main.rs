@@ -344,12 +344,43 @@ fn main() -> Result<(), anyhow::Error> {
             #[cfg(any(feature = "redis-events", feature = "websocket-events"))]
             tearbot_common::indexer_events::start_stream(xeon.arc_clone_state()).await;
 
-            tokio::time::sleep(Duration::from_secs(u64::MAX)).await;
+            shutdown_signal().await;
+            xeon.state().begin_shutdown();
+            info!(
+                "Shutting down, waiting for {} pending tracked notification tasks",
+                xeon.state().pending_notification_tasks()
+            );
+            let unfinished = xeon
+                .state()
+                .wait_for_notification_tasks(SHUTDOWN_TIMEOUT)
+                .await;
+            if unfinished > 0 {
+                log::warn!("{unfinished} notification tasks didn't finish before shutdown");
+            }
 
             Ok(())
         })
 }
 
+/// How long to wait for in-flight notifications before exiting
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = sigterm.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Failed to listen for Ctrl+C");
+}
+
 async fn get_db() -> Result<Database, anyhow::Error> {
     let client_uri = std::env::var("MONGODB_URI").expect("MONGODB_URI not set");
     let options = ClientOptions::parse(&client_uri).await?;
@@ -8,10 +8,12 @@ use std::{
 
 use async_trait::async_trait;
 use itertools::Itertools;
+use tearbot_common::indexer_events::{indexer_stream_health, IndexerEvent};
+use tearbot_common::intear_events::events::trade::trade_swap::TradeSwapEvent;
 #[allow(unused_imports)]
 use tearbot_common::near_primitives::types::AccountId;
 use tearbot_common::utils::{apis::parse_meme_cooking_link, rpc::account_exists};
-use tearbot_common::utils::{tokens::MEME_COOKING_CONTRACT_ID, SLIME_USER_ID};
+use tearbot_common::utils::{format_duration, tokens::MEME_COOKING_CONTRACT_ID, SLIME_USER_ID};
 use tearbot_common::{
     bot_commands::{MessageCommand, TgCommand},
     mongodb::bson::DateTime,
@@ -243,6 +245,105 @@ impl XeonBotModule for HubModule {
         match command {
             MessageCommand::None => {
                 if user_id == SLIME_USER_ID && chat_id.is_user() {
+                    // Testing tool: runs a trade through the modules that opted in with
+                    // `IndexerEventHandler::supports_simulation` as if it came from the indexer,
+                    // so filters, limits and sending are exercised end to end against the target
+                    // chat's real config. Their notifications to other chats are dropped. Modules
+                    // that send from plain `tokio::spawn` (ft-buybot) can't opt in yet. Takes a
+                    // chat id and a TradeSwapEvent as JSON, e.g. a real event with edited amounts.
+                    if let Some(args) = text.strip_prefix("/simulatetrade") {
+                        const USAGE: &str = "Usage: /simulatetrade <chat id\\> \\{\\.\\.\\.\\}";
+                        let message = match args.trim().split_once(char::is_whitespace) {
+                            Some((target, event)) => match (
+                                target.parse::<i64>(),
+                                serde_json::from_str::<TradeSwapEvent>(event.trim()),
+                            ) {
+                                (Ok(target), Ok(event)) => {
+                                    log::info!("Simulating trade for chat {target}: {event:?}");
+                                    let handlers = bot
+                                        .xeon()
+                                        .dispatch_simulated_indexer_event(
+                                            &IndexerEvent::TradeSwap(event),
+                                            ChatId(target),
+                                        )
+                                        .await;
+                                    format!(
+                                        "Simulated trade was passed to the modules that support simulation \\({handlers}\\), their notifications are limited to `{target}`"
+                                    )
+                                }
+                                (Err(_), _) => format!("Invalid chat id\\. {USAGE}"),
+                                (_, Err(err)) => format!(
+                                    "Invalid TradeSwapEvent JSON: {}\n\n{USAGE}",
+                                    markdown::escape(&err.to_string())
+                                ),
+                            },
+                            None => USAGE.to_string(),
+                        };
+                        let reply_markup = InlineKeyboardMarkup::new(Vec::<Vec<_>>::new());
+                        bot.send_text_message(chat_id.into(), message, reply_markup)
+                            .await?;
+                        return Ok(());
+                    }
+                    if text == "/indexerstatus" {
+                        let streams = indexer_stream_health();
+                        let prices_age = match bot.xeon().prices_age().await {
+                            Some(age) => markdown::escape(&format!("{} ago", format_duration(age))),
+                            None => "never".to_string(),
+                        };
+                        let streams = if streams.is_empty() {
+                            "No event streams are running".to_string()
+                        } else {
+                            streams
+                                .into_iter()
+                                .map(|(stream, health)| {
+                                    format!(
+                                        "{} `{}`: last event {}, {} reconnects",
+                                        if health.connected { "🟢" } else { "🔴" },
+                                        markdown::escape_code(&stream),
+                                        match health.last_event {
+                                            Some(last_event) => markdown::escape(&format!(
+                                                "{} ago",
+                                                format_duration(last_event.elapsed())
+                                            )),
+                                            None => "never".to_string(),
+                                        },
+                                        health.reconnects
+                                    )
+                                })
+                                .join("\n")
+                        };
+                        let message = format!("{streams}\n\nToken prices updated {prices_age}");
+                        let reply_markup = InlineKeyboardMarkup::new(Vec::<Vec<_>>::new());
+                        bot.send_text_message(chat_id.into(), message, reply_markup)
+                            .await?;
+                        return Ok(());
+                    }
+                    if let Some(mode) = text.strip_prefix("/maintenance") {
+                        let message = match mode.trim() {
+                            "on" => {
+                                bot.set_maintenance_mode(true).await?;
+                                log::warn!("Maintenance mode enabled for bot {}", bot.id());
+                                "Maintenance mode enabled, notifications from modules that check the notification limit are silenced".to_string()
+                            }
+                            "off" => {
+                                let suppressed = bot.set_maintenance_mode(false).await?;
+                                log::warn!("Maintenance mode disabled for bot {}, {suppressed} notifications were suppressed", bot.id());
+                                format!("Maintenance mode disabled, {suppressed} notifications were suppressed")
+                            }
+                            _ => format!(
+                                "Maintenance mode is {}\\. Usage: /maintenance on\\|off",
+                                if bot.is_in_maintenance_mode() {
+                                    "on"
+                                } else {
+                                    "off"
+                                }
+                            ),
+                        };
+                        let reply_markup = InlineKeyboardMarkup::new(Vec::<Vec<_>>::new());
+                        bot.send_text_message(chat_id.into(), message, reply_markup)
+                            .await?;
+                        return Ok(());
+                    }
                     if let Some(message) = text.strip_prefix("/spam") {
                         let message = message.trim().to_string();
                         let attachment = if let Some(photo) = user_message.photo() {
@@ -1045,6 +1146,24 @@ impl XeonBotModule for HubModule {
                         }
                     }
                 }
+                if let Some(payload) = data.strip_prefix("cmd-") {
+                    if let Ok(command) = bot.parse_deep_link_payload(payload).await {
+                        for module in bot.xeon().bot_modules().await.iter() {
+                            module
+                                .handle_callback(
+                                    TgCallbackContext::new(
+                                        bot,
+                                        user_id,
+                                        chat_id,
+                                        None,
+                                        &bot.to_callback_data(&command).await,
+                                    ),
+                                    &mut None,
+                                )
+                                .await?;
+                        }
+                    }
+                }
                 if let Some(target_chat_id) = data.strip_prefix("setup-") {
                     if let Ok([target_chat_id, thread_id]) =
                         <[&str; 2]>::try_from(target_chat_id.split('=').collect::<Vec<_>>())
@@ -2346,6 +2465,19 @@ Your withdrawable balance: {}, connected account: {}
                 )
                 .await?;
             }
+            TgCommand::SetNotificationLimitNotice(target_chat_id, enabled) => {
+                if !check_admin_permission_in_chat(context.bot(), target_chat_id, context.user_id())
+                    .await
+                {
+                    return Ok(());
+                }
+                context
+                    .bot()
+                    .set_notification_limit_notice_enabled(target_chat_id.chat_id(), enabled)
+                    .await?;
+                self.open_chat_settings(&mut context, Some(target_chat_id))
+                    .await?;
+            }
             #[allow(unreachable_patterns)]
             _ => {}
         }
@@ -2828,6 +2960,24 @@ Welcome to Int, an AI\\-powered bot for fun and moderation 🤖
                 }
             }
         }
+        let limit_notice_enabled = context
+            .bot()
+            .notification_limit_notice_enabled(target_chat_id.chat_id())
+            .await;
+        buttons.push(vec![InlineKeyboardButton::callback(
+            if limit_notice_enabled {
+                "⚠️ Limit warnings: On"
+            } else {
+                "⚠️ Limit warnings: Off"
+            },
+            context
+                .bot()
+                .to_callback_data(&TgCommand::SetNotificationLimitNotice(
+                    target_chat_id,
+                    !limit_notice_enabled,
+                ))
+                .await,
+        )]);
         if !target_chat_id.is_user() && target_chat_id.thread_id().is_none() {
             buttons.push(vec![InlineKeyboardButton::callback(
                 "👤 Permissions",
@@ -2837,6 +2987,22 @@ Welcome to Int, an AI\\-powered bot for fun and moderation 🤖
                     .await,
             )]);
         }
+        let bot_username = context
+            .bot()
+            .bot()
+            .get_me()
+            .await?
+            .username
+            .clone()
+            .expect("Bot has no username");
+        let settings_link_payload = context
+            .bot()
+            .to_deep_link_payload(&TgCommand::ChatSettings(target_chat_id))
+            .await?;
+        buttons.push(vec![InlineKeyboardButton::copy_text(
+            "🔗 Copy link to these settings",
+            format!("https://t.me/{bot_username}?start=cmd-{settings_link_payload}"),
+        )]);
         buttons.push(vec![InlineKeyboardButton::callback(
             "⬅️ Back",
             context
@@ -248,6 +248,32 @@ impl XeonBotModule for UtilitiesModule {
                     context.edit_or_send(message, buttons).await?;
                     return Ok(());
                 };
+                let token_info = self.xeon.get_token_info(&token_id).await;
+                let total_supply = token_info
+                    .as_ref()
+                    .map(|info| info.total_supply)
+                    .unwrap_or_default();
+                let circulating_supply = token_info
+                    .as_ref()
+                    .map(|info| info.circulating_supply)
+                    .unwrap_or_default();
+                let price = self.xeon.get_price(&token_id).await;
+                // Same as `format_token_amount`: raw units if 10^decimals doesn't fit in u128
+                let (total_supply, circulating_supply, fdv, market_cap) =
+                    match 10u128.checked_pow(metadata.decimals) {
+                        Some(one_token) => (
+                            (total_supply / one_token).to_string(),
+                            (circulating_supply / one_token).to_string(),
+                            format_usd_amount((total_supply / one_token) as f64 * price),
+                            format_usd_amount((circulating_supply / one_token) as f64 * price),
+                        ),
+                        None => (
+                            format!("{total_supply} raw units of"),
+                            format!("{circulating_supply} raw units of"),
+                            "Unknown".to_string(),
+                            "Unknown".to_string(),
+                        ),
+                    };
                 let holders = get_top100_holders(&token_id).await;
                 let mut holders_str = String::new();
                 for (i, (account_id, balance)) in holders.into_iter().take(10).enumerate() {
@@ -283,48 +309,11 @@ Data provided by [FASTNEAR](https://fastnear.com) 💚
                     markdown::escape(&metadata.symbol),
                     name = markdown::escape(&metadata.name),
                     symbol = markdown::escape(&metadata.symbol),
-                    price =
-                        markdown::escape(&format_usd_amount(self.xeon.get_price(&token_id).await)),
-                    total_supply = markdown::escape(
-                        &(self
-                            .xeon
-                            .get_token_info(&token_id)
-                            .await
-                            .map(|info| info.total_supply)
-                            .unwrap_or_default()
-                            / 10u128.pow(metadata.decimals as u32))
-                        .to_string()
-                    ),
-                    fdv = markdown::escape(&format_usd_amount(
-                        (self
-                            .xeon
-                            .get_token_info(&token_id)
-                            .await
-                            .map(|info| info.total_supply)
-                            .unwrap_or_default()
-                            / 10u128.pow(metadata.decimals as u32)) as f64
-                            * self.xeon.get_price(&token_id).await
-                    )),
-                    circulating_supply = markdown::escape(
-                        &(self
-                            .xeon
-                            .get_token_info(&token_id)
-                            .await
-                            .map(|info| info.circulating_supply)
-                            .unwrap_or_default()
-                            / 10u128.pow(metadata.decimals as u32))
-                        .to_string()
-                    ),
-                    market_cap = markdown::escape(&format_usd_amount(
-                        (self
-                            .xeon
-                            .get_token_info(&token_id)
-                            .await
-                            .map(|info| info.circulating_supply)
-                            .unwrap_or_default()
-                            / 10u128.pow(metadata.decimals as u32)) as f64
-                            * self.xeon.get_price(&token_id).await
-                    )),
+                    price = markdown::escape(&format_usd_amount(price)),
+                    total_supply = markdown::escape(&total_supply),
+                    fdv = markdown::escape(&fdv),
+                    circulating_supply = markdown::escape(&circulating_supply),
+                    market_cap = markdown::escape(&market_cap),
                     chart_urls = if let Some(main_pool) = self
                         .xeon
                         .get_token_info(&token_id)
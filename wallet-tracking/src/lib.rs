@@ -48,6 +48,12 @@ impl IndexerEventHandler for WalletTrackingModule {
         }
         Ok(())
     }
+
+    fn supports_simulation(&self) -> bool {
+        // Doesn't keep anything from events, and only sends from
+        // `spawn_notification_task` after `reached_notification_limit`
+        true
+    }
 }
 
 impl WalletTrackingModule {
@@ -91,7 +97,7 @@ impl WalletTrackingModule {
                     let new_owner_id = event.new_owner_id.clone();
                     let token_id = event.token_id.clone();
                     let amount = event.amount;
-                    tokio::spawn(async move {
+                    self.xeon.spawn_notification_task(async move {
                         let Some(bot) = xeon.bot(&bot_id) else {
                             return;
                         };
@@ -160,7 +166,7 @@ impl WalletTrackingModule {
                     let token_ids = event.token_ids.clone();
                     let token_prices = event.token_prices_near.clone();
 
-                    tokio::spawn(async move {
+                    self.xeon.spawn_notification_task(async move {
                         let Some(bot) = xeon.bot(&bot_id) else {
                             return;
                         };
@@ -247,7 +253,7 @@ impl WalletTrackingModule {
                     let trader = event.trader.clone();
                     let balance_changes = event.balance_changes.clone();
 
-                    tokio::spawn(async move {
+                    self.xeon.spawn_notification_task(async move {
                         let Some(bot) = xeon.bot(&bot_id) else {
                             return;
                         };
@@ -323,7 +329,7 @@ impl WalletTrackingModule {
                     let signer_id = event.signer_id.clone();
                     let receiver_id = event.receiver_id.clone();
 
-                    tokio::spawn(async move {
+                    self.xeon.spawn_notification_task(async move {
                         let Some(bot) = xeon.bot(&bot_id) else {
                             return;
                         };